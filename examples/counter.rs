@@ -1,5 +1,5 @@
 use conrod_core::{self, widget, widget_ids, Labelable, Positionable, Sizeable, Widget};
-use conrod_experiments_rs::program;
+use conrod_experiments_rs::program::{self, ControlFlow};
 use glium;
 use std;
 
@@ -9,7 +9,7 @@ const FONT_PATH: &str = "data/fonts/NotoSans/NotoSans-Regular.ttf";
 
 fn main() {
     // Init the program
-    let mut prog = program::Program::new(
+    let mut prog: program::Program = program::Program::new(
         "Conrod counter",
         WIDTH,
         HEIGHT,
@@ -42,6 +42,7 @@ fn main() {
         {
             count += 1;
         }
+        ControlFlow::Continue
     };
 
     // Run forever our program.
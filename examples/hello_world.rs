@@ -1,5 +1,5 @@
 use conrod_core::{self, color, widget, widget_ids, Colorable, Positionable, Widget};
-use conrod_experiments_rs::program;
+use conrod_experiments_rs::program::{self, ControlFlow};
 use glium;
 use std;
 
@@ -9,7 +9,7 @@ const FONT_PATH: &str = "data/fonts/NotoSans/NotoSans-Regular.ttf";
 
 fn main() {
     // Init the program
-    let mut prog = program::Program::new(
+    let mut prog: program::Program = program::Program::new(
         "Conrod Hello World",
         WIDTH,
         HEIGHT,
@@ -32,6 +32,7 @@ fn main() {
             .color(color::WHITE)
             .font_size(32)
             .set(ids.text, ui);
+        ControlFlow::Continue
     };
 
     // Run forever our program.
@@ -1,5 +1,5 @@
 use conrod_core::{self, color, widget, widget_ids, Colorable, Positionable, Sizeable, Widget};
-use conrod_experiments_rs::program;
+use conrod_experiments_rs::program::{self, ControlFlow};
 use glium;
 use image;
 use std;
@@ -9,7 +9,7 @@ const HEIGHT: u32 = 480;
 
 fn main() {
     // Init the program
-    let mut prog = program::Program::new(
+    let mut prog: program::Program = program::Program::new(
         "Conrod image example",
         WIDTH,
         HEIGHT,
@@ -18,7 +18,7 @@ fn main() {
 
     // Load our image from files, convert it into a texture for widgets.
     let raw_image = load_raw_image("data/rust.png");
-    let texture = glium::texture::Texture2d::new(&prog.display.0, raw_image).unwrap();
+    let texture = glium::texture::Texture2d::new(&prog.backend.display().0, raw_image).unwrap();
     let (w, h) = (texture.width(), texture.height());
 
     // Create a hashmap containing our image data for the widgets.
@@ -39,6 +39,7 @@ fn main() {
             .w_h(w as f64, h as f64)
             .middle()
             .set(ids.texture, ui);
+        ControlFlow::Continue
     };
 
     // Run forever our program.
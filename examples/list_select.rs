@@ -1,7 +1,7 @@
 use conrod_core::{
     self, widget, widget_ids, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget,
 };
-use conrod_experiments_rs::program;
+use conrod_experiments_rs::program::{self, ControlFlow};
 use glium;
 use std;
 
@@ -11,7 +11,7 @@ const FONT_PATH: &str = "data/fonts/NotoSans/NotoSans-Regular.ttf";
 
 fn main() {
     // Init the program
-    let mut prog = program::Program::new(
+    let mut prog: program::Program = program::Program::new(
         "Conrod select list",
         WIDTH,
         HEIGHT,
@@ -105,6 +105,7 @@ fn main() {
         if let Some(s) = scrollbar {
             s.set(ui);
         }
+        ControlFlow::Continue
     };
 
     // Run forever our program.
@@ -1,104 +1,644 @@
 use conrod_core;
 use conrod_winit;
 use glium::{self, glutin, Surface};
+use image;
+use conrod_winit::WinitWindow;
 use std;
 
-pub struct Program {
+/// A backend abstracts the rendering operations a [`Program`] needs, so that a
+/// glium, vulkano or future backend can be swapped in without touching the
+/// widget code. It owns the window/surface and whatever renderer draws conrod
+/// primitives onto it.
+pub trait RenderBackend {
+    /// The window type, used by the main loop to convert winit events into
+    /// conrod `Input`s.
+    type Window: conrod_winit::WinitWindow;
+    /// The image/texture type stored in a `conrod_core::image::Map`.
+    type Image;
+
+    /// Build the backend (surface + renderer) for the given window.
+    fn from_window(window: glutin::WindowBuilder, events_loop: &glutin::EventsLoop) -> Self;
+
+    /// The window, borrowed for winit event conversion.
+    fn window(&self) -> &Self::Window;
+
+    /// Acquire the surface for the next frame, e.g. the next swapchain image.
+    /// Returns `false` when the surface is out of date and must be recreated
+    /// before drawing (`conrod_vulkano`'s acquire can report this on resize);
+    /// the caller should then call [`resize`](RenderBackend::resize) and retry.
+    /// A glium backend has nothing to acquire and simply returns `true`.
+    fn begin_frame(&mut self) -> bool;
+
+    /// Recreate the surface/swapchain for the given window size, called after a
+    /// `Resized` event or when [`begin_frame`](RenderBackend::begin_frame)
+    /// reported the surface out of date.
+    fn resize(&mut self, size: (u32, u32));
+
+    /// Upload the given primitives into the renderer's buffers.
+    fn fill<P>(&mut self, primitives: P, image_map: &conrod_core::image::Map<Self::Image>)
+    where
+        P: conrod_core::render::PrimitiveWalker;
+
+    /// Clear, draw the previously filled primitives and present the frame
+    /// acquired by [`begin_frame`](RenderBackend::begin_frame).
+    fn present(&mut self, image_map: &conrod_core::image::Map<Self::Image>);
+}
+
+/// Value returned by the widget closure (and by key handlers) to drive the main
+/// loop: either keep running, or break out of [`Program::run`] with a value.
+pub enum ControlFlow<T> {
+    Continue,
+    Break(T),
+}
+
+/// A global key binding: invoked with the `Ui` when its key is pressed.
+type KeyHandler<T> = Box<dyn FnMut(&mut conrod_core::Ui) -> ControlFlow<T>>;
+
+pub struct Program<B: RenderBackend = GliumBackend, T = ()> {
     pub ui: conrod_core::Ui,
-    pub display: GliumDisplayWinitWrapper,
+    pub backend: B,
     event_loop: EventLoop,
     glium_events_loop: glutin::EventsLoop,
-    renderer: conrod_glium::Renderer,
+    key_handlers: Vec<(glutin::VirtualKeyCode, KeyHandler<T>)>,
+    redraw: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+/// A cheap, cloneable handle letting user code (e.g. from inside the widget
+/// closure) ask the `Program` to render at least one more frame, keeping an
+/// animation going while the UI would otherwise go idle.
+#[derive(Clone)]
+pub struct RedrawHandle {
+    flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    proxy: glutin::EventsLoopProxy,
 }
 
-enum Continuation {
-    Stop,
+impl RedrawHandle {
+    /// Force the main loop to render another frame instead of blocking. The
+    /// event-loop proxy wakes the loop even when it is parked in `run_forever`
+    /// waiting for an OS event, so a redraw requested from another thread takes
+    /// effect immediately.
+    pub fn request_redraw(&self) {
+        self.flag.store(true, std::sync::atomic::Ordering::SeqCst);
+        let _ = self.proxy.wakeup();
+    }
+}
+
+enum Continuation<T> {
+    Stop(Option<T>),
     Continue,
 }
 
-impl Program {
-    pub fn new(title: &str, width: u32, height: u32, refresh_time: std::time::Duration) -> Program {
+impl<T> Program<GliumBackend, T> {
+    pub fn new(
+        title: &str,
+        width: u32,
+        height: u32,
+        refresh_time: std::time::Duration,
+    ) -> Program<GliumBackend, T> {
+        Program::with_backend(title, width, height, refresh_time)
+    }
+
+    /// Build a `Program` on a true headless glium context, so UIs can be
+    /// rendered with no window at all — handy for golden-image tests and for
+    /// generating documentation screenshots with [`render_to_image`].
+    ///
+    /// Unlike a hidden window this needs only a usable GL context, not a mapped
+    /// surface on a display server.
+    ///
+    /// [`render_to_image`]: Program::render_to_image
+    pub fn headless(
+        width: u32,
+        height: u32,
+        refresh_time: std::time::Duration,
+    ) -> Program<GliumBackend, T> {
+        let glium_events_loop = glutin::EventsLoop::new();
+        let backend = GliumBackend::headless(width, height);
+        let redraw = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        Program {
+            ui: conrod_core::UiBuilder::new([width as f64, height as f64]).build(),
+            event_loop: EventLoop::new(refresh_time, redraw.clone()),
+            glium_events_loop: glium_events_loop,
+            backend: backend,
+            key_handlers: Vec::new(),
+            redraw: redraw,
+        }
+    }
+
+    /// Build the widget graph with the given closure and render it into an
+    /// offscreen texture, reading it back as an `RgbaImage`. Returns `None` when
+    /// the primitives have not changed since the last draw.
+    ///
+    /// The closure is run through `set_widgets` first: without it a headless
+    /// `Program` would capture the empty initial scene, since `run` never gets a
+    /// chance to build the widgets.
+    pub fn render_to_image<F>(
+        &mut self,
+        image_map: &conrod_core::image::Map<glium::texture::Texture2d>,
+        f: &mut F,
+    ) -> Option<image::RgbaImage>
+    where
+        F: FnMut(&mut conrod_core::UiCell) -> ControlFlow<T>,
+    {
+        let (width, height) = match self.backend.window().get_inner_size() {
+            Some(size) => size,
+            None => return None,
+        };
+        // Instantiate the widgets before capturing, otherwise we read back the
+        // empty initial scene.
+        self.draw(f);
+        if let Some(primitives) = self.ui.draw_if_changed() {
+            Some(
+                self.backend
+                    .render_to_image(primitives, image_map, (width, height)),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+impl<B: RenderBackend, T> Program<B, T> {
+    /// Build a `Program` backed by the chosen [`RenderBackend`].
+    pub fn with_backend(
+        title: &str,
+        width: u32,
+        height: u32,
+        refresh_time: std::time::Duration,
+    ) -> Program<B, T> {
         let glium_events_loop = glutin::EventsLoop::new();
         let window = glutin::WindowBuilder::new()
             .with_title(title)
             .with_dimensions((width, height).into());
-        let context = glutin::ContextBuilder::new()
-            .with_vsync(true)
-            .with_multisampling(4);
-        let display = glium::Display::new(window, context, &glium_events_loop).unwrap();
-        let display = GliumDisplayWinitWrapper(display);
+        let backend = B::from_window(window, &glium_events_loop);
+        let redraw = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
         Program {
             ui: conrod_core::UiBuilder::new([width as f64, height as f64]).build(),
-            event_loop: EventLoop::new(refresh_time),
+            event_loop: EventLoop::new(refresh_time, redraw.clone()),
             glium_events_loop: glium_events_loop,
-            renderer: conrod_glium::Renderer::new(&display.0).unwrap(),
-            display: display,
+            backend: backend,
+            key_handlers: Vec::new(),
+            redraw: redraw,
+        }
+    }
+
+    /// A handle user code can keep to request continued animation frames from
+    /// inside the widget closure. See [`RedrawHandle`].
+    pub fn redraw_handle(&self) -> RedrawHandle {
+        RedrawHandle {
+            flag: self.redraw.clone(),
+            proxy: self.glium_events_loop.create_proxy(),
         }
     }
 
-    fn draw<F>(&mut self, f: &mut F) -> ()
+    /// Force the main loop to render another frame instead of going idle.
+    pub fn request_redraw(&self) {
+        self.redraw
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Configure how long the loop may block while idle before waking anyway,
+    /// so timer-driven widgets still get a chance to update. `None` (the
+    /// default) lets the loop sleep until the next OS event arrives.
+    pub fn set_max_idle(&mut self, max_idle: Option<std::time::Duration>) {
+        self.event_loop.max_idle = max_idle;
+    }
+
+    /// Register a global keyboard shortcut, evaluated on key press alongside the
+    /// `CloseRequested` handling. Returning `ControlFlow::Break(value)` from the
+    /// handler breaks the main loop and makes `run` yield `Some(value)` — e.g.
+    /// Enter to confirm a selection or Escape to cancel.
+    pub fn on_key<H>(&mut self, key: glutin::VirtualKeyCode, handler: H)
     where
-        F: FnMut(&mut conrod_core::UiCell) -> (),
+        H: FnMut(&mut conrod_core::Ui) -> ControlFlow<T> + 'static,
+    {
+        self.key_handlers.push((key, Box::new(handler)));
+    }
+
+    fn draw<F>(&mut self, f: &mut F) -> ControlFlow<T>
+    where
+        F: FnMut(&mut conrod_core::UiCell) -> ControlFlow<T>,
     {
         // Process higher level events (DoubleClick ...) created by Ui::handle_event.
         let ui_cell = &mut self.ui.set_widgets();
         f(ui_cell)
     }
 
-    fn render<Img>(&mut self, image_map: &conrod_core::image::Map<Img>) -> ()
-    where
-        Img: std::ops::Deref + conrod_glium::TextureDimensions,
-        for<'a> glium::uniforms::Sampler<'a, Img>: glium::uniforms::AsUniformValue,
-    {
+    fn render(&mut self, image_map: &conrod_core::image::Map<B::Image>) -> () {
         if let Some(primitives) = self.ui.draw_if_changed() {
-            self.renderer.fill(&self.display.0, primitives, image_map);
-            let mut target = self.display.0.draw();
-            target.clear_color(0.0, 0.0, 0.0, 1.0); // needs the Surface trait
-            self.renderer
-                .draw(&self.display.0, &mut target, image_map)
-                .unwrap();
-            target.finish().unwrap();
+            self.backend.fill(primitives, image_map);
+            // Acquire the frame; if the surface is out of date, recreate it for
+            // the current window size and retry once.
+            if !self.backend.begin_frame() {
+                if let Some(size) = self.backend.window().get_inner_size() {
+                    self.backend.resize(size);
+                }
+                if !self.backend.begin_frame() {
+                    return;
+                }
+            }
+            self.backend.present(image_map);
         }
     }
 
-    fn process_events(&mut self) -> Continuation {
-        for event in self.event_loop.next(&mut self.glium_events_loop) {
+    fn process_events(&mut self) -> Continuation<T> {
+        let events = self.event_loop.next(&mut self.glium_events_loop);
+        // Move the handlers out so they can borrow `self.ui` mutably below.
+        let mut handlers = std::mem::replace(&mut self.key_handlers, Vec::new());
+        let mut continuation = Continuation::Continue;
+        'events: for event in events {
             // Use the `winit` backend to convert the winit event to a conrod one.
-            if let Some(ev) = conrod_winit::convert_event(event.clone(), &self.display) {
+            if let Some(ev) = conrod_winit::convert_event(event.clone(), self.backend.window()) {
                 self.ui.handle_event(ev);
                 self.event_loop.ui_needs_update = true;
             };
 
             match event {
                 glutin::Event::WindowEvent { event, .. } => match event {
-                    glutin::WindowEvent::CloseRequested => return Continuation::Stop,
+                    glutin::WindowEvent::CloseRequested => {
+                        // Don't clobber a value already chosen by a key handler.
+                        if let Continuation::Continue = continuation {
+                            continuation = Continuation::Stop(None);
+                        }
+                    }
+                    glutin::WindowEvent::Resized(size) => {
+                        let size: (u32, u32) = size.into();
+                        self.backend.resize(size);
+                    }
+                    glutin::WindowEvent::KeyboardInput { input, .. } => {
+                        if input.state == glutin::ElementState::Pressed {
+                            if let Some(key) = input.virtual_keycode {
+                                for &mut (k, ref mut handler) in handlers.iter_mut() {
+                                    if k == key {
+                                        if let ControlFlow::Break(value) = handler(&mut self.ui) {
+                                            // Stop as soon as a value is chosen so a
+                                            // later event can't discard it.
+                                            continuation = Continuation::Stop(Some(value));
+                                            break 'events;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                     _ => (),
                 },
                 _ => (),
             };
         }
-        Continuation::Continue
+        self.key_handlers = handlers;
+        continuation
     }
 
-    pub fn run<Img, F>(&mut self, image_map: &conrod_core::image::Map<Img>, f: &mut F) -> ()
+    /// Run the main loop until the window is closed, the widget closure returns
+    /// `ControlFlow::Break(value)`, or a registered key handler breaks.
+    ///
+    /// Returns `Some(value)` when the loop broke with a value and `None` when
+    /// the window was simply closed.
+    pub fn run<F>(&mut self, image_map: &conrod_core::image::Map<B::Image>, f: &mut F) -> Option<T>
     where
-        Img: std::ops::Deref + conrod_glium::TextureDimensions,
-        for<'a> glium::uniforms::Sampler<'a, Img>: glium::uniforms::AsUniformValue,
-        F: FnMut(&mut conrod_core::UiCell) -> (),
+        F: FnMut(&mut conrod_core::UiCell) -> ControlFlow<T>,
     {
-        'main: loop {
+        loop {
             // Handle all events.
-            if let Continuation::Stop = self.process_events() {
-                break 'main;
+            match self.process_events() {
+                Continuation::Stop(value) => return value,
+                Continuation::Continue => (),
             }
 
-            // Instantiate the widgets.
-            self.draw(f);
+            // Instantiate the widgets, letting the closure break with a value.
+            if let ControlFlow::Break(value) = self.draw(f) {
+                return Some(value);
+            }
 
             // Render the ui and then display it on the screen.
             self.render(image_map);
         }
     }
+
+    /// Run the UI logic on a dedicated worker thread so that building a heavy
+    /// widget tree never blocks frame presentation.
+    ///
+    /// Modeled on conrod's threaded backend: the main thread keeps sole
+    /// ownership of the backend (GL context, `Renderer`) and the winit
+    /// `EventsLoop`. It collects winit events, converts them with
+    /// `conrod_winit::convert_event` and forwards the resulting `Input`s to the
+    /// worker. The worker owns the `Ui`, replays the inputs, runs the user
+    /// closure through `set_widgets` and sends back owned primitives. Only
+    /// `Input` and `render::OwnedPrimitives` (both `Send`) ever cross the
+    /// channel boundary.
+    pub fn run_threaded<F>(mut self, image_map: &conrod_core::image::Map<B::Image>, f: F) -> ()
+    where
+        F: FnMut(&mut conrod_core::UiCell) -> () + Send + 'static,
+    {
+        // Move the `Ui` onto the worker thread, leaving an empty placeholder
+        // behind so that `self` (which still owns the backend) stays valid.
+        let ui = std::mem::replace(
+            &mut self.ui,
+            conrod_core::UiBuilder::new([0.0, 0.0]).build(),
+        );
+        let proxy = self.glium_events_loop.create_proxy();
+        let (event_tx, primitive_rx) = Self::spawn_ui_thread(ui, f, proxy);
+
+        // Prime the worker with one draw and present the resulting frame
+        // synchronously, so a static UI shows its initial frame before the main
+        // loop goes idle in `run_forever` waiting for an OS event.
+        if event_tx.send(conrod_core::event::Input::Redraw).is_ok() {
+            if let Ok(primitives) = primitive_rx.recv() {
+                self.backend.fill(primitives.walk(), image_map);
+                self.present_threaded(image_map);
+            }
+        }
+
+        'main: loop {
+            // Collect winit events and forward them to the worker as `Input`s.
+            for event in self.event_loop.next(&mut self.glium_events_loop) {
+                if let Some(input) =
+                    conrod_winit::convert_event(event.clone(), self.backend.window())
+                {
+                    // If the worker is gone there is nothing left to drive.
+                    if event_tx.send(input).is_err() {
+                        break 'main;
+                    }
+                    self.event_loop.ui_needs_update = true;
+                };
+
+                if let glutin::Event::WindowEvent { event, .. } = &event {
+                    match event {
+                        glutin::WindowEvent::CloseRequested => break 'main,
+                        glutin::WindowEvent::Resized(size) => {
+                            self.backend.resize((*size).into());
+                        }
+                        _ => (),
+                    }
+                };
+            }
+
+            // Coalesce to the most recently produced primitives, dropping any
+            // stale frames still queued in the channel.
+            let mut latest = None;
+            while let Ok(primitives) = primitive_rx.try_recv() {
+                latest = Some(primitives);
+            }
+            if let Some(primitives) = latest {
+                self.backend.fill(primitives.walk(), image_map);
+                self.present_threaded(image_map);
+            }
+        }
+    }
+
+    /// Acquire and present a frame for the threaded loop, recreating the
+    /// surface for the current window size and retrying once if it is out of
+    /// date.
+    fn present_threaded(&mut self, image_map: &conrod_core::image::Map<B::Image>) {
+        if !self.backend.begin_frame() {
+            if let Some(size) = self.backend.window().get_inner_size() {
+                self.backend.resize(size);
+            }
+            if !self.backend.begin_frame() {
+                return;
+            }
+        }
+        self.backend.present(image_map);
+    }
+
+    /// Spawn the worker thread that owns the `Ui` and turns incoming `Input`s
+    /// into `OwnedPrimitives`.
+    ///
+    /// Returns the `Sender` the main thread pushes inputs onto and the
+    /// `Receiver` it pulls owned primitives from. The worker stops when the
+    /// input channel is closed. `proxy` wakes the main loop as soon as a frame
+    /// is sent, so a `run_forever` park (entered once `ui_needs_update` has
+    /// been reset by an earlier, empty drain) doesn't sit on a finished frame
+    /// until an unrelated OS event arrives.
+    fn spawn_ui_thread<F>(
+        mut ui: conrod_core::Ui,
+        mut f: F,
+        proxy: glutin::EventsLoopProxy,
+    ) -> (
+        std::sync::mpsc::Sender<conrod_core::event::Input>,
+        std::sync::mpsc::Receiver<conrod_core::render::OwnedPrimitives>,
+    )
+    where
+        F: FnMut(&mut conrod_core::UiCell) -> () + Send + 'static,
+    {
+        let (event_tx, event_rx) = std::sync::mpsc::channel::<conrod_core::event::Input>();
+        let (primitive_tx, primitive_rx) =
+            std::sync::mpsc::channel::<conrod_core::render::OwnedPrimitives>();
+
+        std::thread::spawn(move || {
+            // Block until at least one input arrives, then drain the rest so we
+            // always rebuild against the freshest state.
+            while let Ok(input) = event_rx.recv() {
+                ui.handle_event(input);
+                while let Ok(input) = event_rx.try_recv() {
+                    ui.handle_event(input);
+                }
+
+                // Process higher level events (DoubleClick ...) and rebuild the
+                // widget tree.
+                {
+                    let ui_cell = &mut ui.set_widgets();
+                    f(ui_cell);
+                }
+
+                // Hand owned primitives back to the render thread and wake it up
+                // in case it has already parked waiting for an OS event.
+                if let Some(primitives) = ui.draw_if_changed() {
+                    if primitive_tx.send(primitives.owned()).is_err() {
+                        break;
+                    }
+                    let _ = proxy.wakeup();
+                }
+            }
+        });
+
+        (event_tx, primitive_rx)
+    }
+}
+
+/// The default [`RenderBackend`], drawing conrod primitives with `conrod_glium`
+/// onto a glium surface — either a real window or a true headless context.
+pub struct GliumBackend {
+    surface: GliumSurface,
+    renderer: conrod_glium::Renderer,
+}
+
+/// The glium surface a [`GliumBackend`] draws onto: a windowed `Display`, or a
+/// windowless headless context used for offscreen capture (see
+/// [`Program::headless`]). A headless context does not require a window to be
+/// mapped, only a usable GL context.
+pub enum GliumSurface {
+    Windowed(GliumDisplayWinitWrapper),
+    Headless {
+        context: glium::HeadlessRenderer,
+        size: (u32, u32),
+    },
+}
+
+impl conrod_winit::WinitWindow for GliumSurface {
+    fn get_inner_size(&self) -> Option<(u32, u32)> {
+        match self {
+            GliumSurface::Windowed(display) => {
+                conrod_winit::WinitWindow::get_inner_size(display)
+            }
+            GliumSurface::Headless { size, .. } => Some(*size),
+        }
+    }
+    fn hidpi_factor(&self) -> f32 {
+        match self {
+            GliumSurface::Windowed(display) => {
+                conrod_winit::WinitWindow::hidpi_factor(display)
+            }
+            GliumSurface::Headless { .. } => 1.0,
+        }
+    }
+}
+
+impl RenderBackend for GliumBackend {
+    type Window = GliumSurface;
+    type Image = glium::texture::Texture2d;
+
+    fn from_window(window: glutin::WindowBuilder, events_loop: &glutin::EventsLoop) -> Self {
+        let context = glutin::ContextBuilder::new()
+            .with_vsync(true)
+            .with_multisampling(4);
+        let display = glium::Display::new(window, context, events_loop).unwrap();
+        let display = GliumDisplayWinitWrapper(display);
+        let renderer = conrod_glium::Renderer::new(&display.0).unwrap();
+        GliumBackend {
+            renderer: renderer,
+            surface: GliumSurface::Windowed(display),
+        }
+    }
+
+    fn window(&self) -> &Self::Window {
+        &self.surface
+    }
+
+    fn begin_frame(&mut self) -> bool {
+        // glium resizes its default framebuffer with the GL context, so there
+        // is no swapchain image to acquire and the frame is always ready.
+        true
+    }
+
+    fn resize(&mut self, _size: (u32, u32)) {
+        // glium tracks the window size through the GL context; nothing to do.
+    }
+
+    fn fill<P>(&mut self, primitives: P, image_map: &conrod_core::image::Map<Self::Image>)
+    where
+        P: conrod_core::render::PrimitiveWalker,
+    {
+        match &self.surface {
+            GliumSurface::Windowed(display) => {
+                self.renderer.fill(&display.0, primitives, image_map)
+            }
+            GliumSurface::Headless { context, .. } => {
+                self.renderer.fill(context, primitives, image_map)
+            }
+        }
+    }
+
+    fn present(&mut self, image_map: &conrod_core::image::Map<Self::Image>) {
+        // Only a windowed surface has a framebuffer to present; a headless
+        // context is captured through `render_to_image` instead.
+        if let GliumSurface::Windowed(display) = &self.surface {
+            let mut target = display.0.draw();
+            target.clear_color(0.0, 0.0, 0.0, 1.0); // needs the Surface trait
+            self.renderer
+                .draw(&display.0, &mut target, image_map)
+                .unwrap();
+            target.finish().unwrap();
+        }
+    }
+}
+
+impl GliumBackend {
+    /// Build a backend on a true headless glium context of the given size, with
+    /// no window surface. Used by [`Program::headless`] for offscreen capture.
+    fn headless(width: u32, height: u32) -> Self {
+        let events_loop = glutin::EventsLoop::new();
+        let context = glutin::ContextBuilder::new()
+            .with_multisampling(4)
+            .build_headless(
+                &events_loop,
+                glutin::dpi::PhysicalSize::new(width as f64, height as f64),
+            )
+            .unwrap();
+        let context = unsafe { context.make_current().unwrap() };
+        let context = glium::HeadlessRenderer::new(context).unwrap();
+        let renderer = conrod_glium::Renderer::new(&context).unwrap();
+        GliumBackend {
+            renderer: renderer,
+            surface: GliumSurface::Headless {
+                context: context,
+                size: (width, height),
+            },
+        }
+    }
+
+    /// The windowed `Display`, for examples that upload textures directly.
+    ///
+    /// Panics on a headless backend, which has no window surface.
+    pub fn display(&self) -> &GliumDisplayWinitWrapper {
+        match &self.surface {
+            GliumSurface::Windowed(display) => display,
+            GliumSurface::Headless { .. } => {
+                panic!("GliumBackend::display called on a headless backend")
+            }
+        }
+    }
+
+    /// Draw the given primitives into an offscreen framebuffer and read the
+    /// pixels back as an `RgbaImage`. Dispatches on the surface so the same
+    /// capture runs against a windowed or a headless facade; matching here
+    /// keeps the `renderer` and `surface` borrows disjoint.
+    fn render_to_image<P>(
+        &mut self,
+        primitives: P,
+        image_map: &conrod_core::image::Map<glium::texture::Texture2d>,
+        size: (u32, u32),
+    ) -> image::RgbaImage
+    where
+        P: conrod_core::render::PrimitiveWalker,
+    {
+        match &self.surface {
+            GliumSurface::Windowed(display) => {
+                capture(&mut self.renderer, &display.0, primitives, image_map, size)
+            }
+            GliumSurface::Headless { context, .. } => {
+                capture(&mut self.renderer, context, primitives, image_map, size)
+            }
+        }
+    }
+}
+
+/// Fill the renderer, draw it into an offscreen framebuffer on `facade` and read
+/// the pixels back as an `RgbaImage`.
+fn capture<F, P>(
+    renderer: &mut conrod_glium::Renderer,
+    facade: &F,
+    primitives: P,
+    image_map: &conrod_core::image::Map<glium::texture::Texture2d>,
+    (width, height): (u32, u32),
+) -> image::RgbaImage
+where
+    F: glium::backend::Facade,
+    P: conrod_core::render::PrimitiveWalker,
+{
+    renderer.fill(facade, primitives, image_map);
+    let texture = glium::texture::Texture2d::empty(facade, width, height).unwrap();
+    {
+        let mut target = glium::framebuffer::SimpleFrameBuffer::new(facade, &texture).unwrap();
+        target.clear_color(0.0, 0.0, 0.0, 1.0); // needs the Surface trait
+        renderer.draw(facade, &mut target, image_map).unwrap();
+    }
+
+    // Read the texture back. glium textures are stored bottom-up, so flip.
+    let raw: glium::texture::RawImage2d<u8> = texture.read();
+    let buffer = image::ImageBuffer::from_raw(raw.width, raw.height, raw.data.into_owned())
+        .expect("texture did not fit the expected RGBA buffer");
+    image::imageops::flip_vertical(&buffer)
 }
 
 pub struct GliumDisplayWinitWrapper(pub glium::Display);
@@ -114,43 +654,122 @@ impl conrod_winit::WinitWindow for GliumDisplayWinitWrapper {
 
 struct EventLoop {
     time_step: std::time::Duration,
-    last_update: std::time::Instant,
+    next_frame_deadline: std::time::Instant,
+    max_idle: Option<std::time::Duration>,
+    redraw: std::sync::Arc<std::sync::atomic::AtomicBool>,
     ui_needs_update: bool,
 }
 
 impl EventLoop {
-    fn new(time_step: std::time::Duration) -> Self {
+    fn new(
+        time_step: std::time::Duration,
+        redraw: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    ) -> Self {
         EventLoop {
             time_step,
-            last_update: std::time::Instant::now(),
+            next_frame_deadline: std::time::Instant::now(),
+            max_idle: None,
+            redraw,
             ui_needs_update: true,
         }
     }
 
-    /// Produce an iterator yielding all available events.
+    /// Collect the events to process this iteration, blocking as much as
+    /// possible so a static UI costs almost no CPU.
+    ///
+    /// While the UI is active (it needs an update or a redraw was requested) we
+    /// `poll_events` and only sleep the remaining time until the next frame
+    /// deadline to keep a smooth 60 FPS. While idle we block in `run_forever`
+    /// until an OS event arrives, waking at most every `max_idle` so timers
+    /// still fire.
     fn next(&mut self, events_loop: &mut glutin::EventsLoop) -> Vec<glutin::Event> {
-        // We don't want to loop any faster than 60 FPS, so wait until it has been at least 16ms
-        // since the last yield.
-        let duration_since_last_update = std::time::Instant::now().duration_since(self.last_update);
-        if duration_since_last_update < self.time_step {
-            std::thread::sleep(self.time_step - duration_since_last_update);
-        }
+        let redraw_requested = self
+            .redraw
+            .swap(false, std::sync::atomic::Ordering::SeqCst);
+        let active = self.ui_needs_update || redraw_requested;
 
-        // Collect all pending events.
         let mut events = Vec::new();
-        events_loop.poll_events(|event| events.push(event));
-
-        // If there are no events and the `Ui` does not need updating, wait for the next event.
-        if events.is_empty() && !self.ui_needs_update {
-            events_loop.run_forever(|event| {
-                events.push(event);
-                glutin::ControlFlow::Break
-            });
+        if active {
+            // React immediately to anything already pending; otherwise pace to
+            // the frame deadline, polling in short steps so an event arriving
+            // mid-sleep is picked up right away instead of waiting out the
+            // whole remaining slice.
+            events_loop.poll_events(|event| events.push(event));
+            while events.is_empty() {
+                let now = std::time::Instant::now();
+                if now >= self.next_frame_deadline {
+                    break;
+                }
+                let step = std::cmp::min(
+                    std::time::Duration::from_millis(1),
+                    self.next_frame_deadline - now,
+                );
+                std::thread::sleep(step);
+                events_loop.poll_events(|event| events.push(event));
+            }
+        } else {
+            events_loop.poll_events(|event| events.push(event));
+            if events.is_empty() {
+                match self.max_idle {
+                    // Wake at most every `max_idle` so timer-driven widgets keep
+                    // ticking, but poll in short steps so an incoming event is
+                    // picked up promptly instead of waiting out the whole period.
+                    Some(max_idle) => {
+                        let idle_deadline = std::time::Instant::now() + max_idle;
+                        while events.is_empty() {
+                            let now = std::time::Instant::now();
+                            if now >= idle_deadline {
+                                break;
+                            }
+                            let step = std::cmp::min(self.time_step, idle_deadline - now);
+                            std::thread::sleep(step);
+                            events_loop.poll_events(|event| events.push(event));
+                        }
+                    }
+                    // Block until the next OS event: near-zero CPU when static.
+                    None => {
+                        events_loop.run_forever(|event| {
+                            events.push(event);
+                            glutin::ControlFlow::Break
+                        });
+                    }
+                }
+            }
         }
 
+        self.next_frame_deadline = std::time::Instant::now() + self.time_step;
         self.ui_needs_update = false;
-        self.last_update = std::time::Instant::now();
 
         events
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use conrod_core::{color, widget, Colorable, Widget};
+
+    // Render a full-canvas colour on a headless context and assert the readback
+    // is the right size and actually contains the drawn (non-background) pixels.
+    // Guards the golden-image capture path `headless` + `render_to_image`.
+    #[test]
+    fn headless_render_to_image_captures_widgets() {
+        let mut program: Program<GliumBackend, ()> =
+            Program::headless(64, 64, std::time::Duration::from_millis(16));
+        let image_map = conrod_core::image::Map::<glium::texture::Texture2d>::new();
+
+        let image = program
+            .render_to_image(&image_map, &mut |ui| {
+                let canvas = ui.widget_id_generator().next();
+                widget::Canvas::new().color(color::RED).set(canvas, ui);
+                ControlFlow::Continue
+            })
+            .expect("a headless frame should be produced");
+
+        assert_eq!(image.dimensions(), (64, 64));
+        assert!(
+            image.pixels().any(|p| p.0 != [0, 0, 0, 255]),
+            "capture should contain the drawn canvas, not just the clear colour",
+        );
+    }
+}